@@ -1,15 +1,28 @@
+use crate::storage::{self, Store, StorageError};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
+use std::path::Path;
 use uuid::Uuid;
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct CheeseRegistry(HashMap<String, CheeseData>);
+#[derive(Debug, Clone)]
+pub struct CheeseRegistry {
+    cheeses: HashMap<String, CheeseData>,
+    store: Option<Store>,
+}
+
+impl PartialEq for CheeseRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cheeses == other.cheeses
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub enum CheeseRegistryError {
     DuplicateCheeseName,
     NoSuchCheeseInRegistry,
+    Storage(String),
 }
 
 impl Error for CheeseRegistryError {}
@@ -21,31 +34,131 @@ impl Display for CheeseRegistryError {
                 f,
                 "Cannot insert cheese, cheese names must be unique across a registry."
             ),
+            Self::Storage(err) => write!(f, "registry storage error: {err}"),
         }
     }
 }
 
+impl From<StorageError> for CheeseRegistryError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err.to_string())
+    }
+}
+
 impl CheeseRegistry {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            cheeses: HashMap::new(),
+            store: None,
+        }
+    }
+
+    /// Open (or create) an on-disk registry, replaying its `cheese/` and
+    /// `rating/` records back into memory so lookups stay as cheap as the
+    /// in-memory-only registry.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CheeseRegistryError> {
+        Self::from_store(Store::open(path)?)
+    }
+
+    /// Open a registry backed by a `Store` a `user::UserRegistry` is
+    /// already using (or vice versa), so both trees of one on-disk
+    /// database stay in sync.
+    pub fn from_store(store: Store) -> Result<Self, CheeseRegistryError> {
+        let cheeses = storage::load_cheeses(&store)?;
+        Ok(Self {
+            cheeses,
+            store: Some(store),
+        })
+    }
+
+    /// The underlying store, if this registry is persisted, so other
+    /// registries (e.g. `user::UserRegistry`) can share the same database.
+    pub fn store(&self) -> Option<&Store> {
+        self.store.as_ref()
+    }
+
+    /// Flush any buffered writes to disk. A no-op for an in-memory registry.
+    pub fn flush(&self) -> Result<(), CheeseRegistryError> {
+        match &self.store {
+            Some(store) => Ok(store.flush()?),
+            None => Ok(()),
+        }
     }
 
     pub fn insert(&mut self, cheese: CheeseData) -> Result<(), CheeseRegistryError> {
-        if self.0.contains_key(&cheese.name) {
-            Err(CheeseRegistryError::DuplicateCheeseName)
-        } else {
-            self.0.insert(cheese.name.clone(), cheese);
-            Ok(())
+        if self.cheeses.contains_key(&cheese.name) {
+            return Err(CheeseRegistryError::DuplicateCheeseName);
+        }
+        if let Some(store) = &self.store {
+            storage::insert_cheese(store, &cheese.name)?;
         }
+        self.cheeses.insert(cheese.name.clone(), cheese);
+        Ok(())
+    }
+
+    pub fn contains(&self, cheese_name: &str) -> bool {
+        self.cheeses.contains_key(cheese_name)
     }
 
     pub fn get_mut(&mut self, cheese_name: &str) -> Result<&mut CheeseData, CheeseRegistryError> {
-        if let Some(cheese) = self.0.get_mut(cheese_name) {
+        if let Some(cheese) = self.cheeses.get_mut(cheese_name) {
             Ok(cheese)
         } else {
             Err(CheeseRegistryError::NoSuchCheeseInRegistry)
         }
     }
+
+    /// Fuzzy-match cheese names against `query`, tolerating up to
+    /// `max_edits` typos. Exact-prefix matches always rank first; the rest
+    /// are ordered by ascending edit distance, then by name length, then
+    /// lexicographically, so the best guess comes first.
+    pub fn search(&self, query: &str, max_edits: u8) -> Vec<CheeseData> {
+        let query = query.to_lowercase();
+
+        let mut matches: Vec<(bool, u8, &CheeseData)> = self
+            .cheeses
+            .values()
+            .filter_map(|cheese| {
+                let name = cheese.name.to_lowercase();
+                if name.starts_with(&query) {
+                    return Some((true, 0, cheese));
+                }
+                bounded_edit_distance(&query, &name, max_edits).map(|edits| (false, edits, cheese))
+            })
+            .collect();
+
+        matches.sort_by(|(a_prefix, a_edits, a_cheese), (b_prefix, b_edits, b_cheese)| {
+            b_prefix
+                .cmp(a_prefix)
+                .then(a_edits.cmp(b_edits))
+                .then(a_cheese.name.len().cmp(&b_cheese.name.len()))
+                .then(a_cheese.name.cmp(&b_cheese.name))
+        });
+
+        matches.into_iter().map(|(_, _, cheese)| cheese.clone()).collect()
+    }
+
+    /// Record `user_id`'s rating of `cheese_name`, persisting it before the
+    /// in-memory map is touched so a failed write never leaves the two out
+    /// of sync.
+    pub fn rate(
+        &mut self,
+        cheese_name: &str,
+        user_id: Uuid,
+        rating: CheeseRating,
+    ) -> Result<(), CheeseRegistryError> {
+        if !self.cheeses.contains_key(cheese_name) {
+            return Err(CheeseRegistryError::NoSuchCheeseInRegistry);
+        }
+        if let Some(store) = &self.store {
+            storage::insert_rating(store, cheese_name, user_id, rating)?;
+        }
+        self.cheeses
+            .get_mut(cheese_name)
+            .expect("checked above: cheese exists in registry")
+            .insert_rating(RegistryCheeseRating(user_id, rating));
+        Ok(())
+    }
 }
 
 impl IntoIterator for CheeseRegistry {
@@ -53,11 +166,11 @@ impl IntoIterator for CheeseRegistry {
     type IntoIter = std::collections::hash_map::IntoValues<String, Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_values()
+        self.cheeses.into_values()
     }
 }
 
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Debug, Clone, Copy, Serialize)]
 pub struct CheeseRating(u8);
 
 #[derive(Debug, PartialEq)]
@@ -90,12 +203,20 @@ impl CheeseRating {
             Ok(Self(rating))
         }
     }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        self.0
+    }
+
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        Self::new(byte).ok()
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct RegistryCheeseRating(pub Uuid, pub CheeseRating);
 
-#[derive(Default, PartialEq, Debug, Clone)]
+#[derive(Default, PartialEq, Debug, Clone, Serialize)]
 pub struct RegistryCheeseRatingMap(HashMap<Uuid, CheeseRating>);
 
 impl RegistryCheeseRatingMap {
@@ -106,6 +227,13 @@ impl RegistryCheeseRatingMap {
     fn get(&self, uuid: Uuid) -> CheeseRating {
         self.0[&uuid]
     }
+
+    /// Iterate the ratings without consuming the map, unlike `IntoIterator`.
+    pub fn iter(&self) -> impl Iterator<Item = RegistryCheeseRating> + '_ {
+        self.0
+            .iter()
+            .map(|(&user_id, &rating)| RegistryCheeseRating(user_id, rating))
+    }
 }
 
 impl IntoIterator for RegistryCheeseRatingMap {
@@ -122,12 +250,28 @@ impl IntoIterator for RegistryCheeseRatingMap {
     }
 }
 
-#[derive(Default, PartialEq, Debug, Clone)]
+#[derive(Default, PartialEq, Debug, Clone, Serialize)]
 pub struct CheeseData {
     pub name: String,
     pub ratings: RegistryCheeseRatingMap,
 }
 
+impl Eq for CheeseData {}
+
+// `ratings` holds a `HashMap`, which has no sensible order of its own, so
+// cheeses are ordered by name alone (e.g. for `requests::all_cheeses`).
+impl PartialOrd for CheeseData {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CheeseData {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+
 impl CheeseData {
     // Constructors for unit testing
     pub fn name(self, name: &str) -> Self {
@@ -137,11 +281,75 @@ impl CheeseData {
         }
     }
 
+    pub fn rating_count(&self) -> usize {
+        self.ratings.0.len()
+    }
+
+    pub fn average_rating(&self) -> Option<f64> {
+        let count = self.rating_count();
+        if count == 0 {
+            return None;
+        }
+
+        let total: u32 = self
+            .ratings
+            .iter()
+            .map(|RegistryCheeseRating(_, rating)| rating.to_byte() as u32)
+            .sum();
+
+        Some(total as f64 / count as f64)
+    }
+
+    /// Counts of ratings 1 through 10, indexed `0..10`.
+    pub fn rating_histogram(&self) -> [u32; 10] {
+        let mut histogram = [0u32; 10];
+        for RegistryCheeseRating(_, rating) in self.ratings.iter() {
+            histogram[(rating.to_byte() - 1) as usize] += 1;
+        }
+        histogram
+    }
+
     pub fn insert_rating(&mut self, rating: RegistryCheeseRating) {
         self.ratings.insert(rating);
     }
 }
 
+/// Levenshtein distance between `query` and `candidate`, bounded by
+/// `max_edits`. Bails out after any row whose smallest value exceeds
+/// `max_edits`, since the smallest value of each later Levenshtein row can
+/// never be lower than that.
+fn bounded_edit_distance(query: &str, candidate: &str, max_edits: u8) -> Option<u8> {
+    let query: Vec<char> = query.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    let max_edits = max_edits as usize;
+
+    let mut previous_row: Vec<usize> = (0..=candidate.len()).collect();
+
+    for (row, &query_char) in query.iter().enumerate() {
+        let mut current_row = vec![0usize; candidate.len() + 1];
+        current_row[0] = row + 1;
+        let mut row_min = current_row[0];
+
+        for (col, &candidate_char) in candidate.iter().enumerate() {
+            let substitution_cost = if query_char == candidate_char { 0 } else { 1 };
+            let value = (previous_row[col] + substitution_cost)
+                .min(previous_row[col + 1] + 1)
+                .min(current_row[col] + 1);
+            current_row[col + 1] = value;
+            row_min = row_min.min(value);
+        }
+
+        if row_min > max_edits {
+            return None;
+        }
+
+        previous_row = current_row;
+    }
+
+    let distance = previous_row[candidate.len()];
+    (distance <= max_edits).then_some(distance as u8)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,4 +461,75 @@ mod tests {
         assert_ne!(registry_clone, registry);
         Ok(())
     }
+
+    #[test]
+    fn persisted_registry_survives_reopen() -> Result<(), CheeseRegistryError> {
+        let path = std::env::temp_dir().join(format!("cheese_wizard-test-{}", Uuid::new_v4()));
+        let user_id = Uuid::new_v4();
+
+        {
+            let mut registry = CheeseRegistry::open(&path)?;
+            registry.insert(CheeseData::default().name("Chedder"))?;
+            registry.rate("Chedder", user_id, CheeseRating::new(8).unwrap())?;
+            registry.flush()?;
+        }
+
+        let mut reopened = CheeseRegistry::open(&path)?;
+        let cheese = reopened.get_mut("Chedder")?;
+        assert_eq!(cheese.ratings.get(user_id), CheeseRating::new(8).unwrap());
+
+        std::fs::remove_dir_all(&path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn search_ranks_prefix_matches_before_typos() -> Result<(), CheeseRegistryError> {
+        let mut registry = CheeseRegistry::new();
+        registry.insert(CheeseData::default().name("Cheddar"))?;
+        registry.insert(CheeseData::default().name("Brie"))?;
+        registry.insert(CheeseData::default().name("Chevre"))?;
+
+        // "Chevre" is 3 edits from "ched", the same as "Cheddar" and
+        // shorter, so only the prefix rule puts "Cheddar" ahead of it.
+        let results = registry.search("ched", 3);
+
+        assert_eq!(results[0].name, "Cheddar");
+        assert_eq!(results[1].name, "Chevre");
+        assert!(!results.iter().any(|cheese| cheese.name == "Brie"));
+        Ok(())
+    }
+
+    #[test]
+    fn search_tolerates_bounded_typos() -> Result<(), CheeseRegistryError> {
+        let mut registry = CheeseRegistry::new();
+        registry.insert(CheeseData::default().name("Cheddar"))?;
+
+        assert_eq!(registry.search("chedadr", 2)[0].name, "Cheddar");
+        assert!(registry.search("chedadr", 0).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn unrated_cheese_has_no_average() {
+        let cheese = CheeseData::default().name("Chedder");
+
+        assert_eq!(cheese.average_rating(), None);
+        assert_eq!(cheese.rating_count(), 0);
+        assert_eq!(cheese.rating_histogram(), [0; 10]);
+    }
+
+    #[test]
+    fn rating_aggregates_summarize_every_rating() {
+        let mut cheese = CheeseData::default().name("Chedder");
+        cheese.insert_rating(RegistryCheeseRating(Uuid::new_v4(), CheeseRating::new(4).unwrap()));
+        cheese.insert_rating(RegistryCheeseRating(Uuid::new_v4(), CheeseRating::new(8).unwrap()));
+
+        assert_eq!(cheese.rating_count(), 2);
+        assert_eq!(cheese.average_rating(), Some(6.0));
+
+        let histogram = cheese.rating_histogram();
+        assert_eq!(histogram[3], 1);
+        assert_eq!(histogram[7], 1);
+        assert_eq!(histogram.iter().sum::<u32>(), 2);
+    }
 }
@@ -0,0 +1,323 @@
+//! A client for talking to the cheese API, mirroring how RPC clients split
+//! a blocking "send and confirm" call from a fire-and-forget async send.
+//!
+//! [`CheeseClient`] is the synchronous half: it retries transient
+//! transport failures with exponential backoff and surfaces the server's
+//! typed errors on a definitive 4xx. [`AsyncCheeseClient`] is the async
+//! half: it issues the same requests once and ignores the response.
+//! [`InProcessClient`] drives [`crate::server::app`] directly through
+//! `tower::ServiceExt::oneshot`, the way the integration tests already do;
+//! [`HttpClient`]/[`AsyncHttpClient`] talk to a real running server.
+
+use crate::cheese::{CheeseData, CheeseRating, CheeseRegistryError, RatingBoundsError};
+use crate::requests::{CheeseRatingRequest, NewCheeseRequest};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use std::error::Error;
+use std::fmt::Display;
+use std::time::Duration;
+use tower::ServiceExt;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// A network error, an unreadable response, or any status without a
+    /// typed mapping below; [`CheeseClient`] implementations retry these.
+    Transport(String),
+    Registry(CheeseRegistryError),
+    Rating(RatingBoundsError),
+}
+
+impl Error for ClientError {}
+impl Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transport(message) => write!(f, "cheese client transport error: {message}"),
+            Self::Registry(err) => Display::fmt(err, f),
+            Self::Rating(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+/// How hard a [`CheeseClient`] retries a transient failure before giving up.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+fn with_retry<T>(
+    policy: &RetryPolicy,
+    mut attempt: impl FnMut() -> Result<T, ClientError>,
+) -> Result<T, ClientError> {
+    let mut backoff = policy.initial_backoff;
+    let mut last_err = None;
+
+    for _ in 0..policy.max_attempts.max(1) {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(err @ ClientError::Transport(_)) => {
+                last_err = Some(err);
+                std::thread::sleep(backoff);
+                backoff *= 2;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_err.expect("max_attempts is always at least 1"))
+}
+
+/// The blocking half: send a request and retry until it's confirmed or
+/// definitively rejected.
+pub trait CheeseClient {
+    fn create_cheese(&self, request: NewCheeseRequest) -> Result<(), ClientError>;
+    fn rate_cheese(&self, request: CheeseRatingRequest) -> Result<(), ClientError>;
+    fn list_cheeses(&self) -> Result<Vec<CheeseData>, ClientError>;
+}
+
+/// The fire-and-forget half: send the request once and discard the
+/// response, without checking whether the server accepted it.
+pub trait AsyncCheeseClient {
+    fn create_cheese(&self, request: NewCheeseRequest) -> impl std::future::Future<Output = ()>;
+    fn rate_cheese(
+        &self,
+        request: CheeseRatingRequest,
+    ) -> impl std::future::Future<Output = ()>;
+}
+
+/// Status-code mapping shared by every transport: a definitive 4xx maps to
+/// the crate's own typed errors instead of a generic transport failure.
+fn classify_status(status: StatusCode, rating: u8) -> Option<ClientError> {
+    match status {
+        StatusCode::NOT_FOUND => {
+            Some(ClientError::Registry(CheeseRegistryError::NoSuchCheeseInRegistry))
+        }
+        StatusCode::CONFLICT => {
+            Some(ClientError::Registry(CheeseRegistryError::DuplicateCheeseName))
+        }
+        // The validation that produced this status is pure and lives in
+        // `CheeseRating::new`, so we can recover the exact bound it tripped
+        // without needing the server to echo it back.
+        StatusCode::UNPROCESSABLE_ENTITY => CheeseRating::new(rating)
+            .err()
+            .map(ClientError::Rating),
+        _ => None,
+    }
+}
+
+/// Drives [`crate::server::app`] in-process via `oneshot`, without a
+/// socket. Useful for tests and for callers embedded in the same process
+/// as the server.
+pub struct InProcessClient {
+    router: Router,
+    policy: RetryPolicy,
+}
+
+impl InProcessClient {
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            policy: RetryPolicy::default(),
+        }
+    }
+
+    fn send(&self, request: Request<Body>) -> Result<(StatusCode, Vec<u8>), ClientError> {
+        futures::executor::block_on(async {
+            let response = self
+                .router
+                .clone()
+                .oneshot(request)
+                .await
+                .map_err(|err| ClientError::Transport(err.to_string()))?;
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .map_err(|err| ClientError::Transport(err.to_string()))?;
+            Ok((status, body.to_vec()))
+        })
+    }
+}
+
+impl CheeseClient for InProcessClient {
+    fn create_cheese(&self, request: NewCheeseRequest) -> Result<(), ClientError> {
+        with_retry(&self.policy, || {
+            let body = serde_json::to_vec(&request).expect("NewCheeseRequest always serializes");
+            let (status, _) = self.send(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .expect("well-formed request"),
+            )?;
+
+            if status.is_success() {
+                return Ok(());
+            }
+            Err(classify_status(status, 0)
+                .unwrap_or_else(|| ClientError::Transport(format!("unexpected status {status}"))))
+        })
+    }
+
+    fn rate_cheese(&self, request: CheeseRatingRequest) -> Result<(), ClientError> {
+        with_retry(&self.policy, || {
+            let body = serde_json::to_vec(&request).expect("CheeseRatingRequest always serializes");
+            let (status, _) = self.send(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/cheeses/{}/ratings", request.cheese))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .expect("well-formed request"),
+            )?;
+
+            if status.is_success() {
+                return Ok(());
+            }
+            Err(classify_status(status, request.rating)
+                .unwrap_or_else(|| ClientError::Transport(format!("unexpected status {status}"))))
+        })
+    }
+
+    fn list_cheeses(&self) -> Result<Vec<CheeseData>, ClientError> {
+        with_retry(&self.policy, || {
+            let (status, body) = self.send(
+                Request::builder()
+                    .uri("/api/cheeses")
+                    .body(Body::empty())
+                    .expect("well-formed request"),
+            )?;
+
+            if !status.is_success() {
+                return Err(classify_status(status, 0)
+                    .unwrap_or_else(|| ClientError::Transport(format!("unexpected status {status}"))));
+            }
+            serde_json::from_slice(&body)
+                .map_err(|err| ClientError::Transport(err.to_string()))
+        })
+    }
+}
+
+/// Talks to a real, already-running `server::app()` over HTTP.
+pub struct HttpClient {
+    base_url: String,
+    http: reqwest::blocking::Client,
+    policy: RetryPolicy,
+}
+
+impl HttpClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: reqwest::blocking::Client::new(),
+            policy: RetryPolicy::default(),
+        }
+    }
+}
+
+impl CheeseClient for HttpClient {
+    fn create_cheese(&self, request: NewCheeseRequest) -> Result<(), ClientError> {
+        with_retry(&self.policy, || {
+            let response = self
+                .http
+                .post(format!("{}/api/cheeses", self.base_url))
+                .json(&request)
+                .send()
+                .map_err(|err| ClientError::Transport(err.to_string()))?;
+
+            if response.status().is_success() {
+                return Ok(());
+            }
+            Err(classify_status(response.status(), 0)
+                .unwrap_or_else(|| ClientError::Transport(format!("unexpected status {}", response.status()))))
+        })
+    }
+
+    fn rate_cheese(&self, request: CheeseRatingRequest) -> Result<(), ClientError> {
+        with_retry(&self.policy, || {
+            let response = self
+                .http
+                .post(format!(
+                    "{}/api/cheeses/{}/ratings",
+                    self.base_url, request.cheese
+                ))
+                .json(&request)
+                .send()
+                .map_err(|err| ClientError::Transport(err.to_string()))?;
+
+            if response.status().is_success() {
+                return Ok(());
+            }
+            Err(classify_status(response.status(), request.rating)
+                .unwrap_or_else(|| ClientError::Transport(format!("unexpected status {}", response.status()))))
+        })
+    }
+
+    fn list_cheeses(&self) -> Result<Vec<CheeseData>, ClientError> {
+        with_retry(&self.policy, || {
+            let response = self
+                .http
+                .get(format!("{}/api/cheeses", self.base_url))
+                .send()
+                .map_err(|err| ClientError::Transport(err.to_string()))?;
+
+            if !response.status().is_success() {
+                return Err(classify_status(response.status(), 0).unwrap_or_else(|| {
+                    ClientError::Transport(format!("unexpected status {}", response.status()))
+                }));
+            }
+            response
+                .json()
+                .map_err(|err| ClientError::Transport(err.to_string()))
+        })
+    }
+}
+
+/// Talks to a real server over HTTP without checking whether the
+/// request was accepted.
+pub struct AsyncHttpClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl AsyncHttpClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+impl AsyncCheeseClient for AsyncHttpClient {
+    async fn create_cheese(&self, request: NewCheeseRequest) {
+        let _ = self
+            .http
+            .post(format!("{}/api/cheeses", self.base_url))
+            .json(&request)
+            .send()
+            .await;
+    }
+
+    async fn rate_cheese(&self, request: CheeseRatingRequest) {
+        let _ = self
+            .http
+            .post(format!(
+                "{}/api/cheeses/{}/ratings",
+                self.base_url, request.cheese
+            ))
+            .json(&request)
+            .send()
+            .await;
+    }
+}
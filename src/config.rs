@@ -0,0 +1,147 @@
+//! Deployment configuration, loaded from a TOML manifest so the service
+//! doesn't need recompiling to change where it binds or what it starts
+//! with.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+const DEFAULT_HOST: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+const DEFAULT_PORT: u16 = 0;
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Manifest {
+    #[serde(default)]
+    host: String,
+    #[serde(default)]
+    port: u16,
+    #[serde(default)]
+    store_path: String,
+    #[serde(default)]
+    seed_cheeses: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+}
+
+impl Error for ManifestError {}
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read manifest file: {err}"),
+            Self::Toml(err) => write!(f, "could not parse manifest: {err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ManifestError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ManifestError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+impl FromStr for Manifest {
+    type Err = ManifestError;
+
+    fn from_str(input: &str) -> Result<Self, ManifestError> {
+        Ok(toml::from_str(input)?)
+    }
+}
+
+impl Manifest {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ManifestError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// The address to bind the server to, falling back to
+    /// `127.0.0.1:0` (an OS-assigned port) when the manifest leaves
+    /// `host`/`port` unset.
+    pub fn bind_addr(&self) -> SocketAddr {
+        let host = if self.host.is_empty() {
+            IpAddr::V4(DEFAULT_HOST)
+        } else {
+            self.host
+                .parse()
+                .unwrap_or(IpAddr::V4(DEFAULT_HOST))
+        };
+        SocketAddr::new(host, self.port)
+    }
+
+    /// Where to persist the `CheeseRegistry`, or `None` to keep it
+    /// in-memory. An empty string in the manifest means the same as the
+    /// field being absent.
+    pub fn store_path(&self) -> Option<&str> {
+        (!self.store_path.is_empty()).then_some(self.store_path.as_str())
+    }
+
+    pub fn seed_cheeses(&self) -> &[String] {
+        &self.seed_cheeses
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: DEFAULT_PORT,
+            store_path: String::new(),
+            seed_cheeses: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_manifest_falls_back_to_defaults() {
+        let manifest: Manifest = "".parse().unwrap();
+
+        assert_eq!(
+            manifest.bind_addr(),
+            SocketAddr::new(IpAddr::V4(DEFAULT_HOST), DEFAULT_PORT)
+        );
+        assert_eq!(manifest.store_path(), None);
+        assert!(manifest.seed_cheeses().is_empty());
+    }
+
+    #[test]
+    fn manifest_reads_every_field() {
+        let manifest: Manifest = r#"
+            host = "0.0.0.0"
+            port = 8080
+            store_path = "/var/lib/cheese_wizard"
+            seed_cheeses = ["Chedder", "Brie"]
+            "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            manifest.bind_addr(),
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 8080)
+        );
+        assert_eq!(manifest.store_path(), Some("/var/lib/cheese_wizard"));
+        assert_eq!(manifest.seed_cheeses(), ["Chedder", "Brie"]);
+    }
+
+    #[test]
+    fn empty_string_fields_are_treated_as_absent() {
+        let manifest: Manifest = "host = \"\"\nstore_path = \"\"\n".parse().unwrap();
+
+        assert_eq!(manifest.bind_addr().ip(), IpAddr::V4(DEFAULT_HOST));
+        assert_eq!(manifest.store_path(), None);
+    }
+}
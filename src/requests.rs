@@ -1,17 +1,15 @@
-use crate::cheese::{
-    CheeseData, CheeseRating, CheeseRegistry, CheeseRegistryError, RegistryCheeseRating,
-};
-use crate::user::{UserCheeseRating, UserData};
-use serde::Deserialize;
+use crate::cheese::{CheeseData, CheeseRating, CheeseRegistry, CheeseRegistryError};
+use crate::user::{UserCheeseRating, UserData, UserDataError};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct CheeseRatingRequest {
     pub rating: u8,
     pub cheese: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct NewCheeseRequest {
     pub name: String,
 }
@@ -21,10 +19,16 @@ pub fn rate_cheese(
     user: &mut UserData,
     registry: &mut CheeseRegistry,
 ) -> Result<(), Box<dyn Error>> {
-    let cheese = registry.get_mut(&request.cheese)?;
+    if !registry.contains(&request.cheese) {
+        return Err(Box::new(CheeseRegistryError::NoSuchCheeseInRegistry));
+    }
     let rating = CheeseRating::new(request.rating)?;
-    cheese.insert_rating(RegistryCheeseRating(user.id, rating));
-    user.insert_rating(UserCheeseRating(request.cheese, rating))?;
+    if user.has_rated(&request.cheese) {
+        return Err(Box::new(UserDataError::DuplicateCheeseName));
+    }
+    registry.rate(&request.cheese, user.id, rating)?;
+    user.insert_rating(UserCheeseRating(request.cheese, rating))
+        .expect("checked above: user has not already rated this cheese");
     Ok(())
 }
 
@@ -41,3 +45,25 @@ pub fn all_cheeses(registry: &CheeseRegistry) -> Vec<CheeseData> {
     cheeses.sort();
     cheeses
 }
+
+/// A cheese needs at least this many ratings before it's trusted enough to
+/// show up on the leaderboard.
+const MIN_RATINGS_FOR_LEADERBOARD: usize = 1;
+
+/// The `n` cheeses with the highest average rating, ignoring any cheese
+/// that hasn't crossed [`MIN_RATINGS_FOR_LEADERBOARD`] yet.
+pub fn top_cheeses(registry: &CheeseRegistry, n: usize) -> Vec<CheeseData> {
+    let mut cheeses: Vec<CheeseData> = registry
+        .clone()
+        .into_iter()
+        .filter(|cheese| cheese.rating_count() >= MIN_RATINGS_FOR_LEADERBOARD)
+        .collect();
+
+    cheeses.sort_by(|a, b| {
+        b.average_rating()
+            .partial_cmp(&a.average_rating())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    cheeses.truncate(n);
+    cheeses
+}
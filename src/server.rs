@@ -1,7 +1,191 @@
-use axum::{routing::get, Router};
+use crate::cheese::{CheeseData, CheeseRegistry, CheeseRegistryError};
+use crate::config::Manifest;
+use crate::requests::{self, CheeseRatingRequest, NewCheeseRequest};
+use crate::user::{UserData, UserDataError, UserRegistry};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Callers that want their ratings remembered across requests identify
+/// themselves with this header, holding a `Uuid`. Callers that omit it are
+/// treated as anonymous one-off raters, so the same cheese can still be
+/// rated by more than one caller.
+const USER_ID_HEADER: &str = "x-user-id";
+
+/// The typo tolerance used by the `/api/search` route.
+const SEARCH_MAX_EDITS: u8 = 2;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+#[derive(Clone)]
+struct AppState {
+    registry: Arc<RwLock<CheeseRegistry>>,
+    users: Arc<RwLock<UserRegistry>>,
+}
 
 pub fn app() -> Router {
-    Router::new().route("/api", get(|| async { "" }))
+    app_with_registry(CheeseRegistry::new())
+}
+
+fn app_with_registry(registry: CheeseRegistry) -> Router {
+    // Share the registry's store, if it has one, so a restart remembers
+    // users the same way it remembers cheeses.
+    let users = match registry.store() {
+        Some(store) => UserRegistry::from_store(store.clone())
+            .expect("opening a store CheeseRegistry already opened cannot fail"),
+        None => UserRegistry::new(),
+    };
+
+    let state = AppState {
+        registry: Arc::new(RwLock::new(registry)),
+        users: Arc::new(RwLock::new(users)),
+    };
+
+    Router::new()
+        .route("/api", get(|| async { "" }))
+        .route("/api/cheeses", get(list_cheeses).post(create_cheese))
+        .route("/api/cheeses/:name/ratings", axum::routing::post(rate_cheese))
+        .route("/api/search", get(search_cheeses))
+        .with_state(state)
+}
+
+/// Boot the service from a loaded [`Manifest`]: build (or open) the
+/// registry it describes, seed it, and serve on the configured address.
+pub async fn run(manifest: Manifest) -> Result<(), Box<dyn Error>> {
+    let mut registry = match manifest.store_path() {
+        Some(path) => CheeseRegistry::open(path)?,
+        None => CheeseRegistry::new(),
+    };
+
+    for name in manifest.seed_cheeses() {
+        requests::create_new_cheese(
+            NewCheeseRequest {
+                name: name.clone(),
+            },
+            &mut registry,
+        )?;
+    }
+
+    axum::Server::bind(&manifest.bind_addr())
+        .serve(app_with_registry(registry).into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Wraps whatever error a request handler in [`crate::requests`] returned so
+/// it can be mapped to the right HTTP status instead of always bailing 500.
+struct ApiError(Box<dyn Error>);
+
+impl From<CheeseRegistryError> for ApiError {
+    fn from(err: CheeseRegistryError) -> Self {
+        Self(Box::new(err))
+    }
+}
+
+impl From<Box<dyn Error>> for ApiError {
+    fn from(err: Box<dyn Error>) -> Self {
+        Self(err)
+    }
+}
+
+impl From<UserDataError> for ApiError {
+    fn from(err: UserDataError) -> Self {
+        Self(Box::new(err))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0.downcast_ref::<CheeseRegistryError>() {
+            Some(CheeseRegistryError::NoSuchCheeseInRegistry) => StatusCode::NOT_FOUND,
+            Some(CheeseRegistryError::DuplicateCheeseName) => StatusCode::CONFLICT,
+            Some(CheeseRegistryError::Storage(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            None => match self.0.downcast_ref::<UserDataError>() {
+                Some(UserDataError::DuplicateCheeseName) => StatusCode::CONFLICT,
+                Some(UserDataError::Storage(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+                None => {
+                    if self
+                        .0
+                        .downcast_ref::<crate::cheese::RatingBoundsError>()
+                        .is_some()
+                    {
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    } else {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                }
+            },
+        };
+
+        (status, self.0.to_string()).into_response()
+    }
+}
+
+async fn list_cheeses(State(state): State<AppState>) -> Json<Vec<CheeseData>> {
+    let registry = state.registry.read().unwrap();
+    Json(requests::all_cheeses(&registry))
+}
+
+async fn create_cheese(
+    State(state): State<AppState>,
+    Json(request): Json<NewCheeseRequest>,
+) -> Result<StatusCode, ApiError> {
+    let mut registry = state.registry.write().unwrap();
+    requests::create_new_cheese(request, &mut registry)?;
+    Ok(StatusCode::CREATED)
+}
+
+async fn search_cheeses(
+    State(state): State<AppState>,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+) -> Json<Vec<CheeseData>> {
+    let registry = state.registry.read().unwrap();
+    Json(registry.search(&q, SEARCH_MAX_EDITS))
+}
+
+/// Pulls the rater's id out of [`USER_ID_HEADER`], if present and well-formed.
+fn user_id_from_headers(headers: &HeaderMap) -> Option<Uuid> {
+    headers
+        .get(USER_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| Uuid::parse_str(value).ok())
+}
+
+async fn rate_cheese(
+    State(state): State<AppState>,
+    Path(cheese): Path<String>,
+    headers: HeaderMap,
+    Json(mut request): Json<CheeseRatingRequest>,
+) -> Result<StatusCode, ApiError> {
+    request.cheese = cheese;
+
+    let mut registry = state.registry.write().unwrap();
+
+    match user_id_from_headers(&headers) {
+        Some(user_id) => {
+            let mut users = state.users.write().unwrap();
+            let user = users.get_or_insert(user_id)?;
+            requests::rate_cheese(request, user, &mut registry)?;
+        }
+        // No known caller, so rate on behalf of a one-off anonymous user
+        // instead of the single global one every other caller would share.
+        None => {
+            let mut user = UserData::new();
+            requests::rate_cheese(request, &mut user, &mut registry)?;
+        }
+    }
+
+    Ok(StatusCode::OK)
 }
 
 #[cfg(test)]
@@ -10,6 +194,7 @@ mod tests {
         body::Body,
         http::{Request, StatusCode},
     };
+    use serde_json::json;
     use tower::ServiceExt;
 
     use super::*;
@@ -23,4 +208,194 @@ mod tests {
 
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn creating_and_listing_cheeses() {
+        let app = app();
+
+        let create = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "name": "Chedder" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(create.status(), StatusCode::CREATED);
+
+        let list = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/cheeses")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(list.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn creating_a_duplicate_cheese_is_a_conflict() {
+        let app = app();
+        let body = || Body::from(json!({ "name": "Chedder" }).to_string());
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/cheeses")
+                .header("content-type", "application/json")
+                .body(body())
+                .unwrap()
+        };
+
+        app.clone().oneshot(request()).await.unwrap();
+        let second = app.oneshot(request()).await.unwrap();
+
+        assert_eq!(second.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn manifest_seeded_registry_is_served() {
+        let manifest: Manifest = r#"seed_cheeses = ["Chedder", "Brie"]"#.parse().unwrap();
+        let mut registry = CheeseRegistry::new();
+        for name in manifest.seed_cheeses() {
+            requests::create_new_cheese(
+                NewCheeseRequest {
+                    name: name.clone(),
+                },
+                &mut registry,
+            )
+            .unwrap();
+        }
+
+        let response = app_with_registry(registry)
+            .oneshot(
+                Request::builder()
+                    .uri("/api/cheeses")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn search_finds_a_typo_tolerant_match() {
+        let app = app();
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "name": "Cheddar" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/search?q=ched")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn rating_an_unknown_cheese_is_not_found() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses/Chedder/ratings")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "rating": 5, "cheese": "" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn distinct_callers_can_each_rate_the_same_cheese() {
+        let app = app();
+        let rate_request = |user_id: uuid::Uuid| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/cheeses/Chedder/ratings")
+                .header("content-type", "application/json")
+                .header("x-user-id", user_id.to_string())
+                .body(Body::from(json!({ "rating": 5, "cheese": "" }).to_string()))
+                .unwrap()
+        };
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "name": "Chedder" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let first = app
+            .clone()
+            .oneshot(rate_request(uuid::Uuid::new_v4()))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+
+        let second = app.oneshot(rate_request(uuid::Uuid::new_v4())).await.unwrap();
+        assert_eq!(second.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn the_same_caller_rating_twice_is_a_conflict() {
+        let app = app();
+        let user_id = uuid::Uuid::new_v4();
+        let rate_request = || {
+            Request::builder()
+                .method("POST")
+                .uri("/api/cheeses/Chedder/ratings")
+                .header("content-type", "application/json")
+                .header("x-user-id", user_id.to_string())
+                .body(Body::from(json!({ "rating": 5, "cheese": "" }).to_string()))
+                .unwrap()
+        };
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/cheeses")
+                    .header("content-type", "application/json")
+                    .body(Body::from(json!({ "name": "Chedder" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        app.clone().oneshot(rate_request()).await.unwrap();
+        let second = app.oneshot(rate_request()).await.unwrap();
+
+        assert_eq!(second.status(), StatusCode::CONFLICT);
+    }
 }
@@ -0,0 +1,226 @@
+//! Embedded, transactional persistence for the registry and user state.
+//!
+//! Records live in one ordered `sled` tree under short prefixes
+//! (`cheese/`, `user/`, `rating/`). Cheese and rating keys follow the
+//! prefix with a length-delimited cheese name (rating keys then add the
+//! rater's uuid bytes); user keys follow it with the user's uuid bytes. A
+//! single tree can hold every kind of record while still letting us
+//! range-scan, e.g., every rating for one cheese without touching the
+//! rest of the tree.
+
+use crate::cheese::{CheeseData, CheeseRating, RegistryCheeseRating};
+use crate::user::{UserCheeseRating, UserData};
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use uuid::Uuid;
+
+const CHEESE_PREFIX: &[u8] = b"cheese/";
+const USER_PREFIX: &[u8] = b"user/";
+const RATING_PREFIX: &[u8] = b"rating/";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Backend(sled::Error),
+    Corrupt(&'static str),
+}
+
+impl Error for StorageError {}
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Backend(err) => write!(f, "storage backend error: {err}"),
+            Self::Corrupt(what) => write!(f, "corrupt record in store: {what}"),
+        }
+    }
+}
+
+impl From<sled::Error> for StorageError {
+    fn from(err: sled::Error) -> Self {
+        Self::Backend(err)
+    }
+}
+
+impl From<TransactionError<StorageError>> for StorageError {
+    fn from(err: TransactionError<StorageError>) -> Self {
+        match err {
+            TransactionError::Abort(err) => err,
+            TransactionError::Storage(err) => Self::Backend(err),
+        }
+    }
+}
+
+/// A handle onto the embedded key-value store backing a [`crate::cheese::CheeseRegistry`].
+///
+/// Cloning a `Store` is cheap: it shares the same underlying `sled::Db`,
+/// the same way every `CheeseRegistry` opened against one path ends up
+/// pointed at the same on-disk tree.
+#[derive(Debug, Clone)]
+pub struct Store(sled::Db);
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Ok(Self(sled::open(path)?))
+    }
+
+    pub fn flush(&self) -> Result<(), StorageError> {
+        self.0.flush()?;
+        Ok(())
+    }
+
+    fn db(&self) -> &sled::Db {
+        &self.0
+    }
+}
+
+fn length_delimited(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(4 + bytes.len());
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+    out
+}
+
+pub(crate) fn cheese_key(name: &str) -> Vec<u8> {
+    [CHEESE_PREFIX.to_vec(), length_delimited(name.as_bytes())].concat()
+}
+
+pub(crate) fn user_key(id: Uuid) -> Vec<u8> {
+    [USER_PREFIX, id.as_bytes()].concat()
+}
+
+pub(crate) fn rating_prefix(cheese_name: &str) -> Vec<u8> {
+    [RATING_PREFIX.to_vec(), length_delimited(cheese_name.as_bytes())].concat()
+}
+
+pub(crate) fn rating_key(cheese_name: &str, user_id: Uuid) -> Vec<u8> {
+    let mut key = rating_prefix(cheese_name);
+    key.extend_from_slice(user_id.as_bytes());
+    key
+}
+
+fn decode_rating_key(key: &[u8]) -> Result<(String, Uuid), StorageError> {
+    let rest = key
+        .strip_prefix(RATING_PREFIX)
+        .ok_or(StorageError::Corrupt("rating key prefix"))?;
+    if rest.len() < 4 {
+        return Err(StorageError::Corrupt("rating key truncated"));
+    }
+    let (len_bytes, rest) = rest.split_at(4);
+    let name_len = u32::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+    if rest.len() != name_len + 16 {
+        return Err(StorageError::Corrupt("rating key size"));
+    }
+    let (name_bytes, uuid_bytes) = rest.split_at(name_len);
+    let name = std::str::from_utf8(name_bytes)
+        .map_err(|_| StorageError::Corrupt("rating cheese name"))?
+        .to_owned();
+    let user_id = Uuid::from_slice(uuid_bytes).map_err(|_| StorageError::Corrupt("rating user id"))?;
+    Ok((name, user_id))
+}
+
+/// Rebuild the in-memory cheese map from the `cheese/` and `rating/` trees.
+pub(crate) fn load_cheeses(store: &Store) -> Result<HashMap<String, CheeseData>, StorageError> {
+    let mut cheeses = HashMap::new();
+
+    for entry in store.db().scan_prefix(CHEESE_PREFIX) {
+        let (_, value) = entry?;
+        let name = std::str::from_utf8(&value)
+            .map_err(|_| StorageError::Corrupt("cheese name"))?
+            .to_owned();
+        cheeses.insert(name.clone(), CheeseData::default().name(&name));
+    }
+
+    for entry in store.db().scan_prefix(RATING_PREFIX) {
+        let (key, value) = entry?;
+        let (cheese_name, user_id) = decode_rating_key(&key)?;
+        let rating = value
+            .first()
+            .and_then(|byte| CheeseRating::from_byte(*byte))
+            .ok_or(StorageError::Corrupt("rating byte"))?;
+        if let Some(cheese) = cheeses.get_mut(&cheese_name) {
+            cheese.insert_rating(RegistryCheeseRating(user_id, rating));
+        }
+    }
+
+    Ok(cheeses)
+}
+
+/// Rebuild the in-memory user map from the `user/` and `rating/` trees.
+pub(crate) fn load_users(store: &Store) -> Result<HashMap<Uuid, UserData>, StorageError> {
+    let mut users = HashMap::new();
+
+    for entry in store.db().scan_prefix(USER_PREFIX) {
+        let (key, value) = entry?;
+        let id_bytes = key
+            .strip_prefix(USER_PREFIX)
+            .ok_or(StorageError::Corrupt("user key prefix"))?;
+        let id = Uuid::from_slice(id_bytes).map_err(|_| StorageError::Corrupt("user id"))?;
+        let user = UserData::decode(id, &value).ok_or(StorageError::Corrupt("user record"))?;
+        users.insert(id, user);
+    }
+
+    for entry in store.db().scan_prefix(RATING_PREFIX) {
+        let (key, value) = entry?;
+        let (cheese_name, user_id) = decode_rating_key(&key)?;
+        let rating = value
+            .first()
+            .and_then(|byte| CheeseRating::from_byte(*byte))
+            .ok_or(StorageError::Corrupt("rating byte"))?;
+        if let Some(user) = users.get_mut(&user_id) {
+            user.restore_rating(UserCheeseRating(cheese_name, rating));
+        }
+    }
+
+    Ok(users)
+}
+
+/// Write a new cheese record in one transaction, so a crash mid-write
+/// can never leave a cheese name claimed without its record.
+pub(crate) fn insert_cheese(store: &Store, name: &str) -> Result<(), StorageError> {
+    let key = cheese_key(name);
+    let name_bytes = name.as_bytes().to_vec();
+
+    store
+        .db()
+        .transaction(move |tx| {
+            tx.insert(key.as_slice(), name_bytes.as_slice())?;
+            Ok::<(), ConflictableTransactionError<StorageError>>(())
+        })
+        .map_err(StorageError::from)
+}
+
+/// Record one user's rating of one cheese in one transaction, keyed so
+/// concurrent raters of the same cheese never clobber each other.
+pub(crate) fn insert_rating(
+    store: &Store,
+    cheese_name: &str,
+    user_id: Uuid,
+    rating: CheeseRating,
+) -> Result<(), StorageError> {
+    let key = rating_key(cheese_name, user_id);
+
+    store
+        .db()
+        .transaction(move |tx| {
+            tx.insert(key.as_slice(), &[rating.to_byte()][..])?;
+            Ok::<(), ConflictableTransactionError<StorageError>>(())
+        })
+        .map_err(StorageError::from)
+}
+
+/// Persist a user record, so restarts don't lose who's who even though
+/// their ratings are reconstructed from the `rating/` tree.
+pub(crate) fn insert_user(store: &Store, user: &UserData) -> Result<(), StorageError> {
+    let key = user_key(user.id);
+    let value = user.encode();
+
+    store
+        .db()
+        .transaction(move |tx| {
+            tx.insert(key.as_slice(), value.as_slice())?;
+            Ok::<(), ConflictableTransactionError<StorageError>>(())
+        })
+        .map_err(StorageError::from)
+}
@@ -0,0 +1,281 @@
+use crate::cheese::CheeseRating;
+use crate::storage::{self, Store, StorageError};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserData {
+    pub id: Uuid,
+    name: String,
+    age: u8,
+    pub cheese_ratings: UserCheeseRatingMap,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UserDataError {
+    DuplicateCheeseName,
+    Storage(String),
+}
+
+impl Error for UserDataError {}
+impl Display for UserDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateCheeseName => write!(
+                f,
+                "Cannot insert cheese, cheese names must be unique across the cheese_ratings."
+            ),
+            Self::Storage(err) => write!(f, "user storage error: {err}"),
+        }
+    }
+}
+
+impl From<StorageError> for UserDataError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err.to_string())
+    }
+}
+
+impl UserData {
+    pub fn new() -> Self {
+        Self::with_id(Uuid::new_v4())
+    }
+
+    pub(crate) fn with_id(id: Uuid) -> Self {
+        Self {
+            id,
+            name: "".to_owned(),
+            age: 0,
+            cheese_ratings: UserCheeseRatingMap::default(),
+        }
+    }
+
+    pub(crate) fn insert_rating(
+        &mut self,
+        user_rating: UserCheeseRating,
+    ) -> Result<(), UserDataError> {
+        if self.cheese_ratings.0.contains_key(&user_rating.0) {
+            Err(UserDataError::DuplicateCheeseName)
+        } else {
+            self.cheese_ratings.insert(user_rating);
+            Ok(())
+        }
+    }
+
+    // So callers can check before mutating, instead of racing insert_rating
+    // and then having to undo a registry write that already landed.
+    pub(crate) fn has_rated(&self, cheese_name: &str) -> bool {
+        self.cheese_ratings.0.contains_key(cheese_name)
+    }
+
+    /// Like `insert_rating`, but for replaying already-persisted ratings
+    /// back into memory on load, where the duplicate check is redundant.
+    pub(crate) fn restore_rating(&mut self, user_rating: UserCheeseRating) {
+        self.cheese_ratings.insert(user_rating);
+    }
+
+    // Constructors for unit testing
+    fn name(self, name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..self
+        }
+    }
+
+    fn age(self, age: u8) -> Self {
+        Self { age, ..self }
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.name.len());
+        bytes.push(self.age);
+        bytes.extend_from_slice(self.name.as_bytes());
+        bytes
+    }
+
+    pub(crate) fn decode(id: Uuid, bytes: &[u8]) -> Option<Self> {
+        let (&age, name_bytes) = bytes.split_first()?;
+        let name = std::str::from_utf8(name_bytes).ok()?.to_owned();
+        Some(Self {
+            id,
+            name,
+            age,
+            cheese_ratings: UserCheeseRatingMap::default(),
+        })
+    }
+}
+
+/// The known users, optionally backed by the same embedded store a
+/// `CheeseRegistry` persists to, so restarts don't forget who's who.
+#[derive(Debug, Clone)]
+pub struct UserRegistry {
+    users: HashMap<Uuid, UserData>,
+    store: Option<Store>,
+}
+
+impl PartialEq for UserRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.users == other.users
+    }
+}
+
+impl UserRegistry {
+    pub fn new() -> Self {
+        Self {
+            users: HashMap::new(),
+            store: None,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, UserDataError> {
+        Self::from_store(Store::open(path)?)
+    }
+
+    /// Open a registry backed by a `Store` a `CheeseRegistry` is already
+    /// using, so both trees of one on-disk database stay in sync.
+    pub fn from_store(store: Store) -> Result<Self, UserDataError> {
+        let users = storage::load_users(&store)?;
+        Ok(Self {
+            users,
+            store: Some(store),
+        })
+    }
+
+    /// Look up `id`, creating (and persisting) a brand-new user the first
+    /// time it's seen.
+    pub fn get_or_insert(&mut self, id: Uuid) -> Result<&mut UserData, UserDataError> {
+        if !self.users.contains_key(&id) {
+            let user = UserData::with_id(id);
+            if let Some(store) = &self.store {
+                storage::insert_user(store, &user)?;
+            }
+            self.users.insert(id, user);
+        }
+        Ok(self.users.get_mut(&id).expect("just inserted above"))
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub struct UserCheeseRating(pub String, pub CheeseRating);
+
+#[derive(Default, PartialEq, Debug, Clone)]
+pub struct UserCheeseRatingMap(HashMap<String, CheeseRating>);
+
+impl UserCheeseRatingMap {
+    fn insert(&mut self, UserCheeseRating(user_id, rating): UserCheeseRating) {
+        self.0.insert(user_id, rating);
+    }
+
+    fn get(&self, name: String) -> CheeseRating {
+        self.0[&name]
+    }
+}
+impl IntoIterator for UserCheeseRatingMap {
+    type Item = UserCheeseRating;
+    type IntoIter = std::iter::Map<
+        std::collections::hash_map::IntoIter<String, CheeseRating>,
+        fn((String, CheeseRating)) -> Self::Item,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0
+            .into_iter()
+            .map(|(cheese_name, cheese_rating)| UserCheeseRating(cheese_name, cheese_rating))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_user_data() {
+        let user = UserData::new().name("Jeffery Hugo").age(18);
+
+        assert_eq!(user.name, "Jeffery Hugo");
+        assert_eq!(user.age, 18);
+        assert!(!user.id.is_nil());
+    }
+
+    #[test]
+    fn new_user_has_unique_id() {
+        let user_1 = UserData::new();
+        let user_2 = UserData::new();
+
+        assert_ne!(user_1.id, user_2.id);
+    }
+
+    #[test]
+    fn inserting_a_rating_into_user_data_adds_a_rating() -> Result<(), UserDataError> {
+        let mut user = UserData::new();
+        let cheese_name = "Chedder".to_owned();
+        let rating = UserCheeseRating(cheese_name.clone(), CheeseRating::new(5).unwrap());
+
+        user.insert_rating(rating)?;
+
+        assert_eq!(
+            user.cheese_ratings.get(cheese_name),
+            CheeseRating::new(5).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cheese_names_are_unique_across_user_data_ratings() -> Result<(), UserDataError> {
+        let cheese_1_rating = UserCheeseRating("Foo".to_owned(), CheeseRating::new(5).unwrap());
+        let cheese_2_rating = UserCheeseRating("Bar".to_owned(), CheeseRating::new(5).unwrap());
+
+        let mut user = UserData::new();
+
+        user.insert_rating(cheese_1_rating.clone())?;
+        user.insert_rating(cheese_2_rating.clone())?;
+
+        // The registry accepts and holds working inputs.
+        let registry_vec: Vec<UserCheeseRating> = user.cheese_ratings.clone().into_iter().collect();
+        assert!(registry_vec.contains(&cheese_1_rating));
+        assert!(registry_vec.contains(&cheese_2_rating));
+
+        assert_eq!(
+            Err(UserDataError::DuplicateCheeseName),
+            user.insert_rating(cheese_1_rating)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn has_rated_reflects_existing_ratings() -> Result<(), UserDataError> {
+        let mut user = UserData::new();
+        assert!(!user.has_rated("Chedder"));
+
+        user.insert_rating(UserCheeseRating(
+            "Chedder".to_owned(),
+            CheeseRating::new(5).unwrap(),
+        ))?;
+
+        assert!(user.has_rated("Chedder"));
+        Ok(())
+    }
+
+    #[test]
+    fn user_registry_reuses_and_persists_users() -> Result<(), UserDataError> {
+        let path = std::env::temp_dir().join(format!("cheese_wizard-test-{}", Uuid::new_v4()));
+        let id = Uuid::new_v4();
+
+        {
+            let mut registry = UserRegistry::open(&path)?;
+            registry.get_or_insert(id)?;
+        }
+
+        let mut reopened = UserRegistry::open(&path)?;
+        assert!(reopened.users.contains_key(&id));
+        assert_eq!(reopened.get_or_insert(id)?.id, id);
+
+        std::fs::remove_dir_all(&path).ok();
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::error::Error;
+
+use cheese_wizard::client::{CheeseClient, InProcessClient};
+use cheese_wizard::requests::{CheeseRatingRequest, NewCheeseRequest};
+use cheese_wizard::server;
+
+#[test]
+fn create_then_rate_then_list_a_cheese() -> Result<(), Box<dyn Error>> {
+    let client = InProcessClient::new(server::app());
+
+    client.create_cheese(NewCheeseRequest {
+        name: "Chedder".to_owned(),
+    })?;
+
+    client.rate_cheese(CheeseRatingRequest {
+        rating: 7,
+        cheese: "Chedder".to_owned(),
+    })?;
+
+    let cheeses = client.list_cheeses()?;
+    assert!(cheeses.iter().any(|cheese| cheese.name == "Chedder"));
+
+    Ok(())
+}
+
+#[test]
+fn rating_an_unknown_cheese_surfaces_the_typed_error() {
+    let client = InProcessClient::new(server::app());
+
+    let err = client
+        .rate_cheese(CheeseRatingRequest {
+            rating: 7,
+            cheese: "Nonexistent".to_owned(),
+        })
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        cheese_wizard::cheese::CheeseRegistryError::NoSuchCheeseInRegistry.to_string()
+    );
+}
@@ -1,7 +1,8 @@
 use std::error::Error;
 
 use cheese_wizard::cheese::{
-    CheeseData, CheeseRating, CheeseRegistry, RegistryCheeseRating, RegistryCheeseRatingMap,
+    CheeseData, CheeseRating, CheeseRegistry, CheeseRegistryError, RegistryCheeseRating,
+    RegistryCheeseRatingMap,
 };
 use cheese_wizard::requests::{self, CheeseRatingRequest, NewCheeseRequest};
 use cheese_wizard::user::{UserCheeseRating, UserData};
@@ -36,6 +37,24 @@ fn cheese_rating_request() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn rating_an_unknown_cheese_is_checked_before_the_rating_bound() {
+    let mut user = UserData::new();
+    let mut cheese_registry = CheeseRegistry::new();
+
+    let request = CheeseRatingRequest {
+        rating: 255,
+        cheese: "FooCheeseId".to_string(),
+    };
+
+    let err = requests::rate_cheese(request, &mut user, &mut cheese_registry).unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        CheeseRegistryError::NoSuchCheeseInRegistry.to_string()
+    );
+}
+
 #[test]
 fn new_cheese_request() -> Result<(), Box<dyn Error>> {
     let json_request = r#"
@@ -83,3 +102,21 @@ fn all_cheeses_request() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn top_cheeses_request_ranks_by_average_rating() -> Result<(), Box<dyn Error>> {
+    let mut cheese_registry = CheeseRegistry::new();
+    cheese_registry.insert(CheeseData::default().name("Chedder"))?;
+    cheese_registry.insert(CheeseData::default().name("Brie"))?;
+    cheese_registry.insert(CheeseData::default().name("Unrated"))?;
+
+    cheese_registry.rate("Chedder", UserData::new().id, CheeseRating::new(6).unwrap())?;
+    cheese_registry.rate("Brie", UserData::new().id, CheeseRating::new(9).unwrap())?;
+
+    let leaderboard = requests::top_cheeses(&cheese_registry, 1);
+
+    assert_eq!(leaderboard.len(), 1);
+    assert_eq!(leaderboard[0].name, "Brie");
+
+    Ok(())
+}